@@ -1,15 +1,21 @@
 use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 use prometheus_client::encoding::text::encode;
+use prometheus_client::metrics::counter::Counter;
 use prometheus_client::metrics::family::Family;
 use prometheus_client::metrics::family::MetricConstructor;
+use prometheus_client::metrics::gauge::Gauge;
 use prometheus_client::metrics::histogram::Histogram;
 use prometheus_client::registry::Registry;
 
-use pyo3::exceptions::{PyKeyError, PyRuntimeError};
+use pyo3::exceptions::{PyKeyError, PyRuntimeError, PyValueError};
 use pyo3::prelude::*;
 
-use pyo3::types::PyList;
+use prost::Message;
+use pyo3::types::{PyBytes, PyList};
+use tiny_http::{Header, Response, Server};
 use tracing_subscriber::filter::Targets;
 use tracing_subscriber::prelude::*;
 
@@ -24,23 +30,146 @@ impl MetricConstructor<Histogram> for HistogramConstructor {
     }
 }
 
+/// A sensible default set of bucket edges for latency-in-seconds
+/// histograms, matching the prometheus_client ecosystem convention.
+const DEFAULT_BUCKETS: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
 type HistogramFamily = Family<Vec<(String, String)>, Histogram, HistogramConstructor>;
+type CounterFamily = Family<Vec<(String, String)>, Counter>;
+type GaugeFamily = Family<Vec<(String, String)>, Gauge>;
 
-#[pyclass(name = "Registry")]
-#[derive(Debug)]
-struct PyRegistry {
+/// Per-family map of the last time each label set was observed,
+/// used to evict idle label sets when `idle_timeout` is set.
+type TouchedMap = HashMap<String, HashMap<Vec<(String, String)>, Instant>>;
+
+/// The registry state shared between the Python-facing mutation
+/// methods and the background HTTP scrape server spawned by
+/// [`PyRegistry::serve`].
+#[derive(Debug, Default)]
+struct RegistryState {
     registry: Registry,
     histograms: HashMap<String, HistogramFamily>,
+    counters: HashMap<String, CounterFamily>,
+    gauges: HashMap<String, GaugeFamily>,
+    idle_timeout: Option<Duration>,
+    histogram_touched: TouchedMap,
+    counter_touched: TouchedMap,
+    gauge_touched: TouchedMap,
+}
+
+impl RegistryState {
+    /// Record that `labels` was just observed for the named
+    /// histogram, for later idle-expiry bookkeeping.
+    fn touch_histogram(&mut self, name: &str, labels: &[(String, String)]) {
+        if self.idle_timeout.is_some() {
+            self.histogram_touched
+                .entry(name.to_string())
+                .or_default()
+                .insert(labels.to_vec(), Instant::now());
+        }
+    }
+
+    /// Record that `labels` was just observed for the named counter.
+    fn touch_counter(&mut self, name: &str, labels: &[(String, String)]) {
+        if self.idle_timeout.is_some() {
+            self.counter_touched
+                .entry(name.to_string())
+                .or_default()
+                .insert(labels.to_vec(), Instant::now());
+        }
+    }
+
+    /// Record that `labels` was just observed for the named gauge.
+    fn touch_gauge(&mut self, name: &str, labels: &[(String, String)]) {
+        if self.idle_timeout.is_some() {
+            self.gauge_touched
+                .entry(name.to_string())
+                .or_default()
+                .insert(labels.to_vec(), Instant::now());
+        }
+    }
+
+    /// Evict every label set across all metric families that hasn't
+    /// been touched within `idle_timeout`. A no-op when no
+    /// `idle_timeout` is configured.
+    fn sweep_expired(&mut self) {
+        let Some(idle_timeout) = self.idle_timeout else {
+            return;
+        };
+        let now = Instant::now();
+
+        for (name, touched) in self.histogram_touched.iter_mut() {
+            if let Some(family) = self.histograms.get(name) {
+                touched.retain(|labels, last_seen| {
+                    let expired = now.duration_since(*last_seen) > idle_timeout;
+                    if expired {
+                        family.remove(labels);
+                    }
+                    !expired
+                });
+            }
+        }
+        for (name, touched) in self.counter_touched.iter_mut() {
+            if let Some(family) = self.counters.get(name) {
+                touched.retain(|labels, last_seen| {
+                    let expired = now.duration_since(*last_seen) > idle_timeout;
+                    if expired {
+                        family.remove(labels);
+                    }
+                    !expired
+                });
+            }
+        }
+        for (name, touched) in self.gauge_touched.iter_mut() {
+            if let Some(family) = self.gauges.get(name) {
+                touched.retain(|labels, last_seen| {
+                    let expired = now.duration_since(*last_seen) > idle_timeout;
+                    if expired {
+                        family.remove(labels);
+                    }
+                    !expired
+                });
+            }
+        }
+    }
+}
+
+#[pyclass(name = "Registry")]
+#[derive(Debug, Clone)]
+struct PyRegistry {
+    inner: Arc<Mutex<RegistryState>>,
 }
 
 #[pymethods]
 impl PyRegistry {
+    /// Create a new, empty registry.
+    ///
+    /// `idle_timeout`, if given, is a number of seconds after which a
+    /// label set that hasn't been observed is evicted from every
+    /// metric family, bounding cardinality for long-running
+    /// processes with high-churn label spaces (e.g. per-connection
+    /// or per-request labels). Eviction is swept lazily on `encode`,
+    /// `encode_protobuf`, and each scrape handled by `serve`.
     #[new]
-    fn __init__() -> Self {
-        PyRegistry {
-            registry: <Registry>::default(),
-            histograms: HashMap::new(),
-        }
+    #[pyo3(signature = (*, idle_timeout=None))]
+    fn __init__(idle_timeout: Option<f64>) -> PyResult<Self> {
+        let idle_timeout = idle_timeout
+            .map(|secs| {
+                if !secs.is_finite() || secs <= 0.0 {
+                    return Err(PyValueError::new_err("idle_timeout must be finite and > 0"));
+                }
+                Ok(Duration::from_secs_f64(secs))
+            })
+            .transpose()?;
+        let state = RegistryState {
+            idle_timeout,
+            ..Default::default()
+        };
+        Ok(PyRegistry {
+            inner: Arc::new(Mutex::new(state)),
+        })
     }
 
     fn __repr__(&self) -> &'static str {
@@ -77,8 +206,9 @@ impl PyRegistry {
     ///
     #[pyo3(signature = (*, name, help, buckets))]
     fn histogram_add(&mut self, name: &str, help: &str, buckets: Vec<f64>) -> PyResult<()> {
+        let mut state = self.inner.lock().unwrap();
         // fail early, without incurring the Box::leak
-        if self.histograms.contains_key(name) {
+        if state.histograms.contains_key(name) {
             return Err(PyKeyError::new_err(format!(
                 "Histogram with name {name} already exists"
             )));
@@ -86,8 +216,8 @@ impl PyRegistry {
         let buckets: &'static [f64] = Box::leak(buckets.into_boxed_slice());
         let cons = HistogramConstructor { buckets };
         let family = HistogramFamily::new_with_constructor(cons);
-        self.histograms.insert(name.to_string(), family.clone());
-        self.registry.register(name, help, family);
+        state.histograms.insert(name.to_string(), family.clone());
+        state.registry.register(name, help, family);
         tracing::debug!("Added histogram '{name}'");
         Ok(())
     }
@@ -99,21 +229,214 @@ impl PyRegistry {
         labels: Bound<'_, PyList>,
         val: f64,
     ) -> PyResult<()> {
-        // First check that we have a histogram with the given name;
-        // we want to fail early without incurring the Python list ->
-        // Rust Vec conversion cost when unncessary.
-        let family = self.histograms
+        let mut state = self.inner.lock().unwrap();
+        let family = state
+            .histograms
             .get(name)
             .ok_or_else(|| PyKeyError::new_err(format!("Histogram '{}' not found", name)))?;
-        // Now extract and observe
         let labels: Vec<(String, String)> = labels.extract()?;
         family.get_or_create(&labels).observe(val);
+        state.touch_histogram(name, &labels);
         Ok(())
     }
 
     /// Retrieve a list of all histogram names
     fn histogram_list(&self) -> Vec<String> {
-        self.histograms.keys().cloned().collect()
+        self.inner.lock().unwrap().histograms.keys().cloned().collect()
+    }
+
+    /// Remove a single label set from a histogram.
+    ///
+    /// Returns `True` if the label set existed and was removed.
+    fn histogram_remove(&mut self, name: &str, labels: Bound<'_, PyList>) -> PyResult<bool> {
+        let mut state = self.inner.lock().unwrap();
+        let family = state
+            .histograms
+            .get(name)
+            .ok_or_else(|| PyKeyError::new_err(format!("Histogram '{}' not found", name)))?;
+        let labels: Vec<(String, String)> = labels.extract()?;
+        let removed = family.remove(&labels);
+        if let Some(touched) = state.histogram_touched.get_mut(name) {
+            touched.remove(&labels);
+        }
+        Ok(removed)
+    }
+
+    /// Drop every label set from a histogram.
+    fn histogram_clear(&mut self, name: &str) -> PyResult<()> {
+        let mut state = self.inner.lock().unwrap();
+        let family = state
+            .histograms
+            .get(name)
+            .ok_or_else(|| PyKeyError::new_err(format!("Histogram '{}' not found", name)))?;
+        family.clear();
+        state.histogram_touched.remove(name);
+        Ok(())
+    }
+
+    /// Add a counter metric to the registry.
+    #[pyo3(signature = (*, name, help))]
+    fn counter_add(&mut self, name: &str, help: &str) -> PyResult<()> {
+        let mut state = self.inner.lock().unwrap();
+        if state.counters.contains_key(name) {
+            return Err(PyKeyError::new_err(format!(
+                "Counter with name {name} already exists"
+            )));
+        }
+        let family = CounterFamily::default();
+        state.counters.insert(name.to_string(), family.clone());
+        state.registry.register(name, help, family);
+        tracing::debug!("Added counter '{name}'");
+        Ok(())
+    }
+
+    /// Increment a counter by one.
+    fn counter_inc(&mut self, name: &str, labels: Bound<'_, PyList>) -> PyResult<()> {
+        let mut state = self.inner.lock().unwrap();
+        let family = state
+            .counters
+            .get(name)
+            .ok_or_else(|| PyKeyError::new_err(format!("Counter '{}' not found", name)))?;
+        let labels: Vec<(String, String)> = labels.extract()?;
+        family.get_or_create(&labels).inc();
+        state.touch_counter(name, &labels);
+        Ok(())
+    }
+
+    /// Increment a counter by the given amount.
+    fn counter_inc_by(&mut self, name: &str, labels: Bound<'_, PyList>, val: u64) -> PyResult<()> {
+        let mut state = self.inner.lock().unwrap();
+        let family = state
+            .counters
+            .get(name)
+            .ok_or_else(|| PyKeyError::new_err(format!("Counter '{}' not found", name)))?;
+        let labels: Vec<(String, String)> = labels.extract()?;
+        family.get_or_create(&labels).inc_by(val);
+        state.touch_counter(name, &labels);
+        Ok(())
+    }
+
+    /// Retrieve a list of all counter names
+    fn counter_list(&self) -> Vec<String> {
+        self.inner.lock().unwrap().counters.keys().cloned().collect()
+    }
+
+    /// Remove a single label set from a counter.
+    ///
+    /// Returns `True` if the label set existed and was removed.
+    fn counter_remove(&mut self, name: &str, labels: Bound<'_, PyList>) -> PyResult<bool> {
+        let mut state = self.inner.lock().unwrap();
+        let family = state
+            .counters
+            .get(name)
+            .ok_or_else(|| PyKeyError::new_err(format!("Counter '{}' not found", name)))?;
+        let labels: Vec<(String, String)> = labels.extract()?;
+        let removed = family.remove(&labels);
+        if let Some(touched) = state.counter_touched.get_mut(name) {
+            touched.remove(&labels);
+        }
+        Ok(removed)
+    }
+
+    /// Drop every label set from a counter.
+    fn counter_clear(&mut self, name: &str) -> PyResult<()> {
+        let mut state = self.inner.lock().unwrap();
+        let family = state
+            .counters
+            .get(name)
+            .ok_or_else(|| PyKeyError::new_err(format!("Counter '{}' not found", name)))?;
+        family.clear();
+        state.counter_touched.remove(name);
+        Ok(())
+    }
+
+    /// Add a gauge metric to the registry.
+    #[pyo3(signature = (*, name, help))]
+    fn gauge_add(&mut self, name: &str, help: &str) -> PyResult<()> {
+        let mut state = self.inner.lock().unwrap();
+        if state.gauges.contains_key(name) {
+            return Err(PyKeyError::new_err(format!(
+                "Gauge with name {name} already exists"
+            )));
+        }
+        let family = GaugeFamily::default();
+        state.gauges.insert(name.to_string(), family.clone());
+        state.registry.register(name, help, family);
+        tracing::debug!("Added gauge '{name}'");
+        Ok(())
+    }
+
+    /// Set a gauge to the given value.
+    fn gauge_set(&mut self, name: &str, labels: Bound<'_, PyList>, val: i64) -> PyResult<()> {
+        let mut state = self.inner.lock().unwrap();
+        let family = state
+            .gauges
+            .get(name)
+            .ok_or_else(|| PyKeyError::new_err(format!("Gauge '{}' not found", name)))?;
+        let labels: Vec<(String, String)> = labels.extract()?;
+        family.get_or_create(&labels).set(val);
+        state.touch_gauge(name, &labels);
+        Ok(())
+    }
+
+    /// Increment a gauge by one.
+    fn gauge_inc(&mut self, name: &str, labels: Bound<'_, PyList>) -> PyResult<()> {
+        let mut state = self.inner.lock().unwrap();
+        let family = state
+            .gauges
+            .get(name)
+            .ok_or_else(|| PyKeyError::new_err(format!("Gauge '{}' not found", name)))?;
+        let labels: Vec<(String, String)> = labels.extract()?;
+        family.get_or_create(&labels).inc();
+        state.touch_gauge(name, &labels);
+        Ok(())
+    }
+
+    /// Decrement a gauge by one.
+    fn gauge_dec(&mut self, name: &str, labels: Bound<'_, PyList>) -> PyResult<()> {
+        let mut state = self.inner.lock().unwrap();
+        let family = state
+            .gauges
+            .get(name)
+            .ok_or_else(|| PyKeyError::new_err(format!("Gauge '{}' not found", name)))?;
+        let labels: Vec<(String, String)> = labels.extract()?;
+        family.get_or_create(&labels).dec();
+        state.touch_gauge(name, &labels);
+        Ok(())
+    }
+
+    /// Retrieve a list of all gauge names
+    fn gauge_list(&self) -> Vec<String> {
+        self.inner.lock().unwrap().gauges.keys().cloned().collect()
+    }
+
+    /// Remove a single label set from a gauge.
+    ///
+    /// Returns `True` if the label set existed and was removed.
+    fn gauge_remove(&mut self, name: &str, labels: Bound<'_, PyList>) -> PyResult<bool> {
+        let mut state = self.inner.lock().unwrap();
+        let family = state
+            .gauges
+            .get(name)
+            .ok_or_else(|| PyKeyError::new_err(format!("Gauge '{}' not found", name)))?;
+        let labels: Vec<(String, String)> = labels.extract()?;
+        let removed = family.remove(&labels);
+        if let Some(touched) = state.gauge_touched.get_mut(name) {
+            touched.remove(&labels);
+        }
+        Ok(removed)
+    }
+
+    /// Drop every label set from a gauge.
+    fn gauge_clear(&mut self, name: &str) -> PyResult<()> {
+        let mut state = self.inner.lock().unwrap();
+        let family = state
+            .gauges
+            .get(name)
+            .ok_or_else(|| PyKeyError::new_err(format!("Gauge '{}' not found", name)))?;
+        family.clear();
+        state.gauge_touched.remove(name);
+        Ok(())
     }
 
     /// Encode the regitry's metrics
@@ -121,13 +444,100 @@ impl PyRegistry {
     /// This method will release the GIL while encoding the registry
     fn encode(&mut self, py: Python<'_>) -> PyResult<String> {
         py.detach(|| {
+            let mut state = self.inner.lock().unwrap();
+            state.sweep_expired();
             let mut buffer = String::new();
-            encode(&mut buffer, &self.registry).map_err(|err| {
+            encode(&mut buffer, &state.registry).map_err(|err| {
                 PyRuntimeError::new_err(format!("Failed to encode registry ({err})"))
             })?;
             Ok(buffer)
         })
     }
+
+    /// Encode the registry's metrics as OpenMetrics protobuf.
+    ///
+    /// Like [`encode`][Self::encode] this releases the GIL while
+    /// encoding, and returns raw `bytes` rather than `str` since the
+    /// protobuf exposition format is binary.
+    fn encode_protobuf<'py>(&mut self, py: Python<'py>) -> PyResult<Bound<'py, PyBytes>> {
+        let buffer = py.detach(|| {
+            let mut state = self.inner.lock().unwrap();
+            state.sweep_expired();
+            let metric_set = prometheus_client::encoding::protobuf::encode(&state.registry)
+                .map_err(|err| {
+                    PyRuntimeError::new_err(format!("Failed to encode registry ({err})"))
+                })?;
+            Ok::<_, PyErr>(metric_set.encode_to_vec())
+        })?;
+        Ok(PyBytes::new(py, &buffer))
+    }
+
+    /// Serve this registry's metrics over HTTP, in the background.
+    ///
+    /// Spawns a detached thread running a minimal HTTP server bound
+    /// to `addr` that responds to `GET /metrics` with the current
+    /// [`encode`][Self::encode] output. A scrape request with an
+    /// `Accept` header naming `application/openmetrics-protobuf` gets
+    /// the [`encode_protobuf`][Self::encode_protobuf] body instead;
+    /// any other (or missing) `Accept` header gets OpenMetrics text.
+    /// The registry is shared with the serving thread behind an
+    /// `Arc<Mutex<_>>`, so metrics mutated from Python after calling
+    /// `serve` are reflected in subsequent scrapes. The GIL is
+    /// released while the server thread is spawned and blocks on
+    /// incoming connections.
+    fn serve(&self, py: Python<'_>, addr: &str) -> PyResult<()> {
+        let inner = Arc::clone(&self.inner);
+        let addr = addr.to_string();
+        py.detach(|| {
+            let server = Server::http(&addr)
+                .map_err(|err| PyRuntimeError::new_err(format!("Failed to bind '{addr}' ({err})")))?;
+            std::thread::spawn(move || {
+                for request in server.incoming_requests() {
+                    if request.url() != "/metrics" {
+                        let _ = request.respond(Response::from_string("Not Found").with_status_code(404));
+                        continue;
+                    }
+                    let wants_protobuf = request.headers().iter().any(|h| {
+                        h.field.equiv("Accept") && h.value.as_str().contains("openmetrics-protobuf")
+                    });
+                    let mut state = inner.lock().unwrap();
+                    state.sweep_expired();
+                    if wants_protobuf {
+                        let buffer = match prometheus_client::encoding::protobuf::encode(&state.registry) {
+                            Ok(metric_set) => metric_set.encode_to_vec(),
+                            Err(err) => {
+                                tracing::warn!("Failed to encode registry as protobuf ({err})");
+                                continue;
+                            }
+                        };
+                        drop(state);
+                        let header = Header::from_bytes(
+                            &b"Content-Type"[..],
+                            &b"application/openmetrics-protobuf; version=1.0.0"[..],
+                        )
+                        .unwrap();
+                        let response = Response::from_data(buffer).with_header(header);
+                        let _ = request.respond(response);
+                    } else {
+                        let mut buffer = String::new();
+                        if let Err(err) = encode(&mut buffer, &state.registry) {
+                            tracing::warn!("Failed to encode registry ({err})");
+                            continue;
+                        }
+                        drop(state);
+                        let header = Header::from_bytes(
+                            &b"Content-Type"[..],
+                            &b"application/openmetrics-text; version=1.0.0; charset=utf-8"[..],
+                        )
+                        .unwrap();
+                        let response = Response::from_string(buffer).with_header(header);
+                        let _ = request.respond(response);
+                    }
+                }
+            });
+            Ok(())
+        })
+    }
 }
 
 #[pymodule]
@@ -149,8 +559,40 @@ mod pyotheus {
             .init();
     }
 
+    /// Generate linearly spaced histogram bucket edges.
+    ///
+    /// Yields `[start + width*i for i in 0..count]`.
+    #[pyfunction]
+    pub(crate) fn linear_buckets(start: f64, width: f64, count: usize) -> PyResult<Vec<f64>> {
+        if count < 1 {
+            return Err(PyValueError::new_err("count must be >= 1"));
+        }
+        if width == 0.0 {
+            return Err(PyValueError::new_err("width must be nonzero"));
+        }
+        Ok((0..count).map(|i| start + width * i as f64).collect())
+    }
+
+    /// Generate exponentially spaced histogram bucket edges.
+    ///
+    /// Yields `[start * factor**i for i in 0..count]`.
+    #[pyfunction]
+    pub(crate) fn exponential_buckets(start: f64, factor: f64, count: usize) -> PyResult<Vec<f64>> {
+        if count < 1 {
+            return Err(PyValueError::new_err("count must be >= 1"));
+        }
+        if start <= 0.0 {
+            return Err(PyValueError::new_err("start must be > 0"));
+        }
+        if factor <= 1.0 {
+            return Err(PyValueError::new_err("factor must be > 1"));
+        }
+        Ok((0..count).map(|i| start * factor.powi(i as i32)).collect())
+    }
+
     #[pymodule_init]
-    fn init(_m: &Bound<'_, PyModule>) -> PyResult<()> {
+    fn init(m: &Bound<'_, PyModule>) -> PyResult<()> {
+        m.add("DEFAULT_BUCKETS", DEFAULT_BUCKETS.to_vec())?;
         Ok(())
     }
 }
@@ -161,7 +603,7 @@ mod tests {
 
     #[test]
     fn test_histogram_list_len() {
-        let mut registry = PyRegistry::__init__();
+        let mut registry = PyRegistry::__init__(None).unwrap();
         let add1 = registry.histogram_add("hist0", "help str", vec![100.0, 200.0, 300.0]);
         let add2 = registry.histogram_add("hist1", "help str", vec![100.0, 200.0, 400.0]);
         assert!(add1.is_ok());
@@ -175,10 +617,244 @@ mod tests {
 
     #[test]
     fn test_histogram_exists() {
-        let mut registry = PyRegistry::__init__();
+        let mut registry = PyRegistry::__init__(None).unwrap();
         let add1 = registry.histogram_add("hist0", "help str", vec![100.0, 200.0, 300.0]);
         assert!(add1.is_ok());
         let add2 = registry.histogram_add("hist0", "help str", vec![100.0, 200.0]);
         assert!(add2.is_err());
     }
+
+    #[test]
+    fn test_counter_list_len() {
+        let mut registry = PyRegistry::__init__(None).unwrap();
+        let add1 = registry.counter_add("counter0", "help str");
+        let add2 = registry.counter_add("counter1", "help str");
+        assert!(add1.is_ok());
+        assert!(add2.is_ok());
+        let mut counter_list = registry.counter_list();
+        counter_list.sort();
+        let mut counter_expected = vec!["counter0", "counter1"];
+        counter_expected.sort();
+        assert_eq!(counter_list, counter_expected);
+    }
+
+    #[test]
+    fn test_counter_exists() {
+        let mut registry = PyRegistry::__init__(None).unwrap();
+        let add1 = registry.counter_add("counter0", "help str");
+        assert!(add1.is_ok());
+        let add2 = registry.counter_add("counter0", "help str");
+        assert!(add2.is_err());
+    }
+
+    #[test]
+    fn test_gauge_list_len() {
+        let mut registry = PyRegistry::__init__(None).unwrap();
+        let add1 = registry.gauge_add("gauge0", "help str");
+        let add2 = registry.gauge_add("gauge1", "help str");
+        assert!(add1.is_ok());
+        assert!(add2.is_ok());
+        let mut gauge_list = registry.gauge_list();
+        gauge_list.sort();
+        let mut gauge_expected = vec!["gauge0", "gauge1"];
+        gauge_expected.sort();
+        assert_eq!(gauge_list, gauge_expected);
+    }
+
+    #[test]
+    fn test_gauge_exists() {
+        let mut registry = PyRegistry::__init__(None).unwrap();
+        let add1 = registry.gauge_add("gauge0", "help str");
+        assert!(add1.is_ok());
+        let add2 = registry.gauge_add("gauge0", "help str");
+        assert!(add2.is_err());
+    }
+
+    #[test]
+    fn test_histogram_clear_missing() {
+        let mut registry = PyRegistry::__init__(None).unwrap();
+        registry
+            .histogram_add("hist0", "help str", vec![100.0, 200.0, 300.0])
+            .unwrap();
+        assert!(registry.histogram_clear("hist0").is_ok());
+        assert!(registry.histogram_clear("nope").is_err());
+    }
+
+    #[test]
+    fn test_counter_clear_missing() {
+        let mut registry = PyRegistry::__init__(None).unwrap();
+        registry.counter_add("counter0", "help str").unwrap();
+        assert!(registry.counter_clear("counter0").is_ok());
+        assert!(registry.counter_clear("nope").is_err());
+    }
+
+    #[test]
+    fn test_gauge_clear_missing() {
+        let mut registry = PyRegistry::__init__(None).unwrap();
+        registry.gauge_add("gauge0", "help str").unwrap();
+        assert!(registry.gauge_clear("gauge0").is_ok());
+        assert!(registry.gauge_clear("nope").is_err());
+    }
+
+    // `*_remove`/`*_clear` take a `Bound<'_, PyList>`, which needs a
+    // live interpreter to construct; these drive the same
+    // `Family::remove`/`Family::clear` calls the pymethods wrap
+    // directly against `RegistryState`, so the eviction behavior
+    // itself is exercised without a GIL.
+
+    #[test]
+    fn test_histogram_remove_and_clear_affect_encode_output() {
+        let mut state = RegistryState::default();
+        let buckets: &'static [f64] = Box::leak(vec![100.0, 200.0].into_boxed_slice());
+        let family = HistogramFamily::new_with_constructor(HistogramConstructor { buckets });
+        state.registry.register("hist0", "help str", family.clone());
+        state.histograms.insert("hist0".to_string(), family.clone());
+        let labels = vec![("path".to_string(), "/a".to_string())];
+        family.get_or_create(&labels).observe(50.0);
+
+        let mut buffer = String::new();
+        encode(&mut buffer, &state.registry).unwrap();
+        assert!(buffer.contains("path=\"/a\""));
+
+        assert!(family.remove(&labels));
+        assert!(!family.remove(&labels));
+
+        let mut buffer = String::new();
+        encode(&mut buffer, &state.registry).unwrap();
+        assert!(!buffer.contains("path=\"/a\""));
+
+        family.get_or_create(&labels).observe(50.0);
+        family.clear();
+        let mut buffer = String::new();
+        encode(&mut buffer, &state.registry).unwrap();
+        assert!(!buffer.contains("path=\"/a\""));
+    }
+
+    #[test]
+    fn test_counter_remove_and_clear_affect_encode_output() {
+        let mut state = RegistryState::default();
+        let family = CounterFamily::default();
+        state.registry.register("counter0", "help str", family.clone());
+        state.counters.insert("counter0".to_string(), family.clone());
+        let labels = vec![("path".to_string(), "/a".to_string())];
+        family.get_or_create(&labels).inc();
+
+        let mut buffer = String::new();
+        encode(&mut buffer, &state.registry).unwrap();
+        assert!(buffer.contains("path=\"/a\""));
+
+        assert!(family.remove(&labels));
+        assert!(!family.remove(&labels));
+
+        let mut buffer = String::new();
+        encode(&mut buffer, &state.registry).unwrap();
+        assert!(!buffer.contains("path=\"/a\""));
+
+        family.get_or_create(&labels).inc();
+        family.clear();
+        let mut buffer = String::new();
+        encode(&mut buffer, &state.registry).unwrap();
+        assert!(!buffer.contains("path=\"/a\""));
+    }
+
+    #[test]
+    fn test_gauge_remove_and_clear_affect_encode_output() {
+        let mut state = RegistryState::default();
+        let family = GaugeFamily::default();
+        state.registry.register("gauge0", "help str", family.clone());
+        state.gauges.insert("gauge0".to_string(), family.clone());
+        let labels = vec![("path".to_string(), "/a".to_string())];
+        family.get_or_create(&labels).set(1);
+
+        let mut buffer = String::new();
+        encode(&mut buffer, &state.registry).unwrap();
+        assert!(buffer.contains("path=\"/a\""));
+
+        assert!(family.remove(&labels));
+        assert!(!family.remove(&labels));
+
+        let mut buffer = String::new();
+        encode(&mut buffer, &state.registry).unwrap();
+        assert!(!buffer.contains("path=\"/a\""));
+
+        family.get_or_create(&labels).set(1);
+        family.clear();
+        let mut buffer = String::new();
+        encode(&mut buffer, &state.registry).unwrap();
+        assert!(!buffer.contains("path=\"/a\""));
+    }
+
+    #[test]
+    fn test_encode_protobuf_roundtrip() {
+        use prometheus_client::encoding::protobuf::openmetrics_data_model::metric_point::Value;
+        use prometheus_client::encoding::protobuf::openmetrics_data_model::MetricSet;
+
+        let mut state = RegistryState::default();
+        let family = CounterFamily::default();
+        state.registry.register("reqs", "help str", family.clone());
+        let labels = vec![("path".to_string(), "/a".to_string())];
+        family.get_or_create(&labels).inc();
+
+        let metric_set = prometheus_client::encoding::protobuf::encode(&state.registry).unwrap();
+        let bytes = metric_set.encode_to_vec();
+        let decoded = MetricSet::decode(bytes.as_slice()).unwrap();
+
+        let family = decoded.metric_families.first().unwrap();
+        assert_eq!("reqs", family.name);
+        let metric = family.metrics.first().unwrap();
+        assert_eq!("path", metric.labels.first().unwrap().name);
+        assert_eq!("/a", metric.labels.first().unwrap().value);
+        match &metric.metric_points.first().unwrap().value {
+            Some(Value::CounterValue(value)) => {
+                assert_eq!(
+                    Some(prometheus_client::encoding::protobuf::openmetrics_data_model::counter_value::Total::IntValue(1)),
+                    value.total
+                );
+            }
+            other => panic!("expected a counter value, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_idle_expiry_sweeps_stale_labels() {
+        let mut state = RegistryState {
+            idle_timeout: Some(Duration::from_millis(1)),
+            ..Default::default()
+        };
+        let family = CounterFamily::default();
+        state.registry.register("reqs", "help str", family.clone());
+        state.counters.insert("reqs".to_string(), family.clone());
+        let labels = vec![("path".to_string(), "/a".to_string())];
+        family.get_or_create(&labels).inc();
+        state.touch_counter("reqs", &labels);
+
+        let mut buffer = String::new();
+        encode(&mut buffer, &state.registry).unwrap();
+        assert!(buffer.contains("path=\"/a\""));
+
+        std::thread::sleep(Duration::from_millis(5));
+        state.sweep_expired();
+        assert!(state.counter_touched.get("reqs").unwrap().is_empty());
+
+        let mut buffer = String::new();
+        encode(&mut buffer, &state.registry).unwrap();
+        assert!(!buffer.contains("path=\"/a\""));
+    }
+
+    #[test]
+    fn test_linear_buckets() {
+        let buckets = pyotheus::linear_buckets(1.0, 2.0, 4).unwrap();
+        assert_eq!(buckets, vec![1.0, 3.0, 5.0, 7.0]);
+        assert!(pyotheus::linear_buckets(1.0, 2.0, 0).is_err());
+        assert!(pyotheus::linear_buckets(1.0, 0.0, 4).is_err());
+    }
+
+    #[test]
+    fn test_exponential_buckets() {
+        let buckets = pyotheus::exponential_buckets(1.0, 2.0, 4).unwrap();
+        assert_eq!(buckets, vec![1.0, 2.0, 4.0, 8.0]);
+        assert!(pyotheus::exponential_buckets(0.0, 2.0, 4).is_err());
+        assert!(pyotheus::exponential_buckets(1.0, 1.0, 4).is_err());
+        assert!(pyotheus::exponential_buckets(1.0, 2.0, 0).is_err());
+    }
 }